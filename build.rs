@@ -1,47 +1,185 @@
 use anyhow::{Context, Result};
 use flate2::read::GzDecoder;
 use reqwest::blocking::get;
+use sha2::{Digest, Sha256};
 use std::env;
 use std::fs;
 use std::io::Cursor;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tar::Archive;
+use zstd::Decoder as ZstdDecoder;
+
+// `build.rs` cannot depend on this crate, so target-asset resolution lives
+// in a standalone file and is pulled into both places with `include!`.
+include!("src/target_asset.rs");
 
 fn main() -> Result<()> {
-    // Download and extract ReadStat binary
-    download_and_extract_readstat()?;
+    // Air-gapped and reproducible ("system" strategy) builds supply their
+    // own binary instead of reaching github.com (the default "download"
+    // strategy).
+    if let Some(binary_path) = vendored_readstat_binary()? {
+        install_vendored_binary(&binary_path)?;
+    } else {
+        download_and_extract_readstat()?;
+    }
 
-    // Tell cargo to re-run this if the build script changes
+    // Tell cargo to re-run this if the build script or vendoring env vars change
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=READSTAT_BINARY");
+    println!("cargo:rerun-if-env-changed=MARY_ELIZABETH_READSTAT_DIR");
+    println!("cargo:rerun-if-env-changed={ALLOW_UNVERIFIED_DOWNLOAD_ENV}");
+
+    Ok(())
+}
+
+/// Resolves a pre-supplied ReadStat binary for the "system" strategy, checked
+/// in order:
+/// - `READSTAT_BINARY`: path directly to the binary file.
+/// - `MARY_ELIZABETH_READSTAT_DIR`: directory containing a binary named
+///   `readstat` (or `readstat.exe` on Windows).
+///
+/// Returns `Ok(None)` when neither is set, so the caller falls back to the
+/// default "download" strategy.
+fn vendored_readstat_binary() -> Result<Option<PathBuf>> {
+    if let Ok(path) = env::var("READSTAT_BINARY") {
+        return Ok(Some(PathBuf::from(path)));
+    }
+
+    if let Ok(dir) = env::var("MARY_ELIZABETH_READSTAT_DIR") {
+        let binary_name = if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+            "readstat.exe"
+        } else {
+            "readstat"
+        };
+        return Ok(Some(Path::new(&dir).join(binary_name)));
+    }
+
+    Ok(None)
+}
+
+/// Copies a vendored binary into `OUT_DIR` and the package's `rust-bin`
+/// directory, mirroring the layout `download_and_extract_readstat` produces,
+/// without making any network request.
+fn install_vendored_binary(binary_path: &Path) -> Result<()> {
+    let out_dir = env::var("OUT_DIR")?;
+    let binary_name = binary_path
+        .file_name()
+        .context("READSTAT_BINARY/MARY_ELIZABETH_READSTAT_DIR must point at a file")?;
+
+    let out_path = Path::new(&out_dir).join(binary_name);
+    fs::copy(binary_path, &out_path).with_context(|| {
+        format!(
+            "Failed to copy vendored ReadStat binary from {}",
+            binary_path.display()
+        )
+    })?;
+
+    let package_binary_path = Path::new("rust-bin")
+        .join("readstat_binary")
+        .join(binary_name);
+    fs::create_dir_all(package_binary_path.parent().unwrap())?;
+    fs::copy(&out_path, &package_binary_path)?;
+
+    println!(
+        "cargo:rustc-env=READSTAT_BINARY={}",
+        package_binary_path.display()
+    );
 
     Ok(())
 }
 
+/// Searches `PATH` for a binary named `binary_name`, used as a fallback when
+/// no prebuilt ReadStat asset is published for the current platform.
+fn which_on_path(binary_name: &str) -> Option<PathBuf> {
+    let path_var = env::var_os("PATH")?;
+    env::split_paths(&path_var)
+        .map(|dir| dir.join(binary_name))
+        .find(|candidate| candidate.is_file())
+}
+
 fn download_and_extract_readstat() -> Result<()> {
     // Determine architecture and platform
     let target_arch = env::var("CARGO_CFG_TARGET_ARCH")?;
     let target_os = env::var("CARGO_CFG_TARGET_OS")?;
-    let target = format!("{}-{}", target_arch, target_os);
+    let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+    let target = format!("{}-{}-{}", target_arch, target_os, target_env);
 
-    println!("Debug: target_arch = {}", target_arch);
-    println!("Debug: target_os = {}", target_os);
     println!("Debug: target = {}", target);
 
-    // Match the correct binary based on platform
-    let (url, archive_ext) = match (target_arch.as_str(), target_os.as_str()) {
-        ("x86_64", "macos") | ("x86_64", "darwin") =>
-            ("https://github.com/curtisalexander/readstat-rs/releases/download/v0.12.2/readstat-v0.12.2-x86_64-apple-darwin.tar.gz", "tar.gz"),
-        ("aarch64", "macos") | ("aarch64", "darwin") =>
-            ("https://github.com/curtisalexander/readstat-rs/releases/download/v0.12.2/readstat-v0.12.2-aarch64-apple-darwin.tar.gz", "tar.gz"),
-        ("x86_64", "windows") =>
-            ("https://github.com/curtisalexander/readstat-rs/releases/download/v0.12.2/readstat-v0.12.2-x86_64-pc-windows-msvc.zip", "zip"),
-        ("x86_64", "linux") =>
-            ("https://github.com/curtisalexander/readstat-rs/releases/download/v0.12.2/readstat-v0.12.2-x86_64-unknown-linux-gnu.tar.gz", "tar.gz"),
-        _ => return Err(anyhow::anyhow!("Unsupported target platform: {}", target)),
+    let binary_name = if target_os == "windows" {
+        "readstat.exe"
+    } else {
+        "readstat"
+    };
+
+    let Some(asset_candidates) = resolve_target_asset_candidates(&target_arch, &target_os, &target_env)
+    else {
+        // No prebuilt asset for this platform — fall back to a `readstat`
+        // binary already on PATH rather than failing the build outright.
+        let existing = which_on_path(binary_name).with_context(|| {
+            format!(
+                "Unsupported target platform ({target}) and no readstat binary found on PATH"
+            )
+        })?;
+        return install_vendored_binary(&existing);
+    };
+
+    // Candidates are listed smallest-first (`.tar.zst` before `.tar.gz`), but
+    // not every release publishes both, so try each in turn and move on to
+    // the next on a 404 rather than failing the build.
+    let (asset_name, response) = asset_candidates
+        .iter()
+        .find_map(|name| {
+            let url = format!(
+                "https://github.com/curtisalexander/readstat-rs/releases/download/v{READSTAT_RELEASE_VERSION}/{name}"
+            );
+            let response = get(&url).ok()?;
+            response.status().is_success().then_some((name.clone(), response))
+        })
+        .context("No suitable asset found for this platform")?;
+
+    let archive_ext = if asset_name.ends_with(".tar.zst") {
+        "tar.zst"
+    } else if asset_name.ends_with(".zip") {
+        "zip"
+    } else {
+        "tar.gz"
     };
 
-    // Download the binary
-    let response = get(url).context("Failed to download ReadStat binary")?;
+    let content = response
+        .bytes()
+        .context("Failed to read ReadStat download")?;
+
+    // Verify integrity against the pinned digest table when we have one for
+    // this asset. readstat-rs publishes no companion checksum file, so
+    // without a KNOWN_ASSET_SHA256 entry there's nothing to verify against —
+    // fail the build rather than silently shipping an unverified binary,
+    // unless the developer has explicitly opted into that via
+    // ALLOW_UNVERIFIED_DOWNLOAD_ENV.
+    match KNOWN_ASSET_SHA256
+        .iter()
+        .find(|(name, _)| *name == asset_name.as_str())
+    {
+        Some((_, expected)) => {
+            let actual = hex::encode(Sha256::digest(&content));
+            if actual != *expected {
+                anyhow::bail!(
+                    "SHA-256 mismatch for {asset_name}: expected {expected}, got {actual}"
+                );
+            }
+        }
+        None if env::var_os(ALLOW_UNVERIFIED_DOWNLOAD_ENV).is_none() => {
+            anyhow::bail!(
+                "No pinned SHA-256 digest for {asset_name}; refusing to install an \
+                 unverified binary. Set {ALLOW_UNVERIFIED_DOWNLOAD_ENV} to override."
+            );
+        }
+        None => println!(
+            "cargo:warning=no pinned checksum for {asset_name}; installing without \
+             integrity verification ({ALLOW_UNVERIFIED_DOWNLOAD_ENV} is set)"
+        ),
+    }
+
     let out_dir = env::var("OUT_DIR")?;
     let dest_path = Path::new(&out_dir).join("readstat_binary");
 
@@ -49,24 +187,25 @@ fn download_and_extract_readstat() -> Result<()> {
 
     // Extract the archive
     if archive_ext == "tar.gz" {
-        let tar = GzDecoder::new(Cursor::new(response.bytes()?));
+        let tar = GzDecoder::new(Cursor::new(&content));
+        let mut archive = Archive::new(tar);
+        archive
+            .unpack(&dest_path)
+            .context("Failed to extract ReadStat binary")?;
+    } else if archive_ext == "tar.zst" {
+        let tar = ZstdDecoder::new(Cursor::new(&content)).context("Failed to open zstd stream")?;
         let mut archive = Archive::new(tar);
         archive
             .unpack(&dest_path)
             .context("Failed to extract ReadStat binary")?;
     } else if archive_ext == "zip" {
-        let mut zip = zip::ZipArchive::new(Cursor::new(response.bytes()?))
-            .context("Failed to open ZIP archive")?;
+        let mut zip =
+            zip::ZipArchive::new(Cursor::new(&content)).context("Failed to open ZIP archive")?;
         zip.extract(&dest_path)
             .context("Failed to extract ZIP archive")?;
     }
 
     // Copy the binary to the final location
-    let binary_name = if target_os == "windows" {
-        "readstat.exe"
-    } else {
-        "readstat"
-    };
     fs::copy(
         dest_path.join(binary_name),
         Path::new(&out_dir).join(binary_name),
@@ -1,5 +1,9 @@
 use pyo3::prelude::*;
+use pyo3::types::PyAny;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+pub mod download_readstat;
 
 #[pyfunction]
 fn get_readstat_path() -> PyResult<PathBuf> {
@@ -14,9 +18,30 @@ fn get_readstat_path() -> PyResult<PathBuf> {
     Ok(path)
 }
 
+/// Ensures the pinned ReadStat binary is installed, skipping the download
+/// when a sufficiently new binary is already cached. Pass `force=True` to
+/// reinstall regardless, and `on_progress` to receive
+/// `on_progress(bytes_downloaded, total_size)` calls as the download
+/// streams in (`total_size` is `None` when the server didn't report one).
+#[pyfunction]
+#[pyo3(signature = (force=false, on_progress=None))]
+fn ensure_readstat_binary(force: bool, on_progress: Option<Py<PyAny>>) -> PyResult<()> {
+    let on_progress: Option<download_readstat::ProgressCallback> = on_progress.map(|callback| {
+        Arc::new(move |downloaded: u64, total: Option<u64>| {
+            Python::with_gil(|py| {
+                let _ = callback.call1(py, (downloaded, total));
+            });
+        }) as download_readstat::ProgressCallback
+    });
+
+    download_readstat::ensure_readstat_binary(force, on_progress)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{e:#}")))
+}
+
 /// A Python module implemented in Rust.
 #[pymodule]
 fn _mary_elizabeth_utils(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(get_readstat_path, m)?)?;
+    m.add_function(wrap_pyfunction!(ensure_readstat_binary, m)?)?;
     Ok(())
 }